@@ -4,9 +4,15 @@ use crate::message::backend::Parse;
 
 pub mod pgoutput;
 pub mod raw;
+pub mod typed;
+#[cfg(feature = "json")]
+pub mod wal2json;
 
 pub use pgoutput::PgOutput;
 pub use raw::Raw;
+pub use typed::{ColumnValue, DecodedValue, Format, RelationCache, TypedTuple};
+#[cfg(feature = "json")]
+pub use wal2json::Wal2Json;
 
 pub trait DecodingPlugin {
     type Message: Parse;
@@ -13,12 +13,18 @@ use crate::replication::DecodingPlugin;
 #[derive(Clone)]
 pub struct PgOutput {
     publications: String,
+    binary: bool,
+    streaming: bool,
+    two_phase: bool,
 }
 
 impl PgOutput {
-    pub fn new(publications: Vec<String>) -> Self {
+    pub fn new(publications: Vec<String>, binary: bool, streaming: bool, two_phase: bool) -> Self {
         Self {
             publications: publications.join(","),
+            binary,
+            streaming,
+            two_phase,
         }
     }
 }
@@ -32,9 +38,22 @@ impl DecodingPlugin for PgOutput {
 
     fn options(&self) -> HashMap<String, String> {
         let mut opts = HashMap::new();
-        // Currently there is only one version
-        opts.insert("proto_version".into(), "1".into());
+        let proto_version = if self.two_phase {
+            "3"
+        } else if self.streaming {
+            "2"
+        } else {
+            "1"
+        };
+        opts.insert("proto_version".into(), proto_version.into());
         opts.insert("publication_names".into(), self.publications.clone());
+        opts.insert("binary".into(), self.binary.to_string());
+        if self.streaming {
+            opts.insert("streaming".into(), "true".into());
+        }
+        if self.two_phase {
+            opts.insert("two_phase".into(), "true".into());
+        }
         opts
     }
 }
@@ -55,6 +74,16 @@ const TUPLE_OLD_TAG: u8 = b'O';
 const TUPLE_DATA_NULL_TAG: u8 = b'n';
 const TUPLE_DATA_TOAST_TAG: u8 = b'u';
 const TUPLE_DATA_TEXT_TAG: u8 = b't';
+const TUPLE_DATA_BINARY_TAG: u8 = b'b';
+const STREAM_START_TAG: u8 = b'S';
+const STREAM_STOP_TAG: u8 = b'E';
+const STREAM_COMMIT_TAG: u8 = b'c';
+const STREAM_ABORT_TAG: u8 = b'A';
+const BEGIN_PREPARE_TAG: u8 = b'b';
+const PREPARE_TAG: u8 = b'P';
+const COMMIT_PREPARED_TAG: u8 = b'K';
+const ROLLBACK_PREPARED_TAG: u8 = b'r';
+const MESSAGE_TAG: u8 = b'M';
 
 // replica identity tags
 const REPLICA_IDENTITY_DEFAULT_TAG: u8 = b'd';
@@ -73,10 +102,31 @@ pub enum LogicalReplicationMessage {
     Update(UpdateBody),
     Delete(DeleteBody),
     Truncate(TruncateBody),
+    StreamStart(StreamStartBody),
+    StreamStop,
+    StreamCommit(StreamCommitBody),
+    StreamAbort(StreamAbortBody),
+    BeginPrepare(BeginPrepareBody),
+    Prepare(PrepareBody),
+    CommitPrepared(CommitPreparedBody),
+    RollbackPrepared(RollbackPreparedBody),
+    Message(MessageBody),
 }
 
 impl Parse for LogicalReplicationMessage {
     fn parse_reader(buf: &mut BufReader<Bytes>) -> io::Result<Self> {
+        Self::parse(buf, false)
+    }
+}
+
+impl LogicalReplicationMessage {
+    /// Parses a logical replication message, treating Relation/Type/Insert/Update/Delete/
+    /// Truncate/Message bodies as carrying a leading transaction xid when `in_stream` is `true`.
+    ///
+    /// `in_stream` should be `true` while the caller is between a `StreamStart` and its
+    /// matching `StreamStop`, which is how protocol version 2 delimits a streamed
+    /// (in-progress) transaction.
+    pub fn parse(buf: &mut BufReader<Bytes>, in_stream: bool) -> io::Result<Self> {
         let tag = buf.read_u8()?;
 
         let logical_replication_message = match tag {
@@ -96,6 +146,7 @@ impl Parse for LogicalReplicationMessage {
                 name: get_cstr(buf)?,
             }),
             RELATION_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
                 let rel_id = buf.read_u32::<BigEndian>()?;
                 let namespace = get_cstr(buf)?;
                 let name = get_cstr(buf)?;
@@ -119,6 +170,7 @@ impl Parse for LogicalReplicationMessage {
                 }
 
                 Self::Relation(RelationBody {
+                    xid,
                     rel_id,
                     namespace,
                     name,
@@ -126,12 +178,17 @@ impl Parse for LogicalReplicationMessage {
                     columns,
                 })
             }
-            TYPE_TAG => Self::Type(TypeBody {
-                id: buf.read_u32::<BigEndian>()?,
-                namespace: get_cstr(buf)?,
-                name: get_cstr(buf)?,
-            }),
+            TYPE_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
+                Self::Type(TypeBody {
+                    xid,
+                    id: buf.read_u32::<BigEndian>()?,
+                    namespace: get_cstr(buf)?,
+                    name: get_cstr(buf)?,
+                })
+            }
             INSERT_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
                 let rel_id = buf.read_u32::<BigEndian>()?;
                 let tag = buf.read_u8()?;
 
@@ -145,9 +202,10 @@ impl Parse for LogicalReplicationMessage {
                     }
                 };
 
-                Self::Insert(InsertBody { rel_id, tuple })
+                Self::Insert(InsertBody { xid, rel_id, tuple })
             }
             UPDATE_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
                 let rel_id = buf.read_u32::<BigEndian>()?;
                 let tag = buf.read_u8()?;
 
@@ -182,6 +240,7 @@ impl Parse for LogicalReplicationMessage {
                 };
 
                 Self::Update(UpdateBody {
+                    xid,
                     rel_id,
                     key_tuple,
                     old_tuple,
@@ -189,6 +248,7 @@ impl Parse for LogicalReplicationMessage {
                 })
             }
             DELETE_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
                 let rel_id = buf.read_u32::<BigEndian>()?;
                 let tag = buf.read_u8()?;
 
@@ -206,9 +266,10 @@ impl Parse for LogicalReplicationMessage {
                     }
                 }
 
-                Self::Delete(DeleteBody { rel_id, key_tuple, old_tuple } )
+                Self::Delete(DeleteBody { xid, rel_id, key_tuple, old_tuple } )
             }
             TRUNCATE_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
                 let relation_len = buf.read_i32::<BigEndian>()?;
                 let options = buf.read_i8()?;
 
@@ -217,7 +278,71 @@ impl Parse for LogicalReplicationMessage {
                     rel_ids.push(buf.read_u32::<BigEndian>()?);
                 }
 
-                Self::Truncate(TruncateBody { options, rel_ids })
+                Self::Truncate(TruncateBody { xid, options, rel_ids })
+            }
+            STREAM_START_TAG => Self::StreamStart(StreamStartBody {
+                xid: buf.read_u32::<BigEndian>()?,
+                first_segment: buf.read_u8()? != 0,
+            }),
+            STREAM_STOP_TAG => Self::StreamStop,
+            STREAM_COMMIT_TAG => Self::StreamCommit(StreamCommitBody {
+                xid: buf.read_u32::<BigEndian>()?,
+                flags: buf.read_i8()?,
+                commit_lsn: buf.read_u64::<BigEndian>()?,
+                end_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+            }),
+            STREAM_ABORT_TAG => Self::StreamAbort(StreamAbortBody {
+                xid: buf.read_u32::<BigEndian>()?,
+                subxid: buf.read_u32::<BigEndian>()?,
+            }),
+            BEGIN_PREPARE_TAG => Self::BeginPrepare(BeginPrepareBody {
+                prepare_lsn: buf.read_u64::<BigEndian>()?,
+                end_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+                xid: buf.read_u32::<BigEndian>()?,
+                gid: get_cstr(buf)?,
+            }),
+            PREPARE_TAG => Self::Prepare(PrepareBody {
+                flags: buf.read_i8()?,
+                prepare_lsn: buf.read_u64::<BigEndian>()?,
+                end_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+                xid: buf.read_u32::<BigEndian>()?,
+                gid: get_cstr(buf)?,
+            }),
+            COMMIT_PREPARED_TAG => Self::CommitPrepared(CommitPreparedBody {
+                flags: buf.read_i8()?,
+                commit_lsn: buf.read_u64::<BigEndian>()?,
+                end_lsn: buf.read_u64::<BigEndian>()?,
+                timestamp: buf.read_i64::<BigEndian>()?,
+                xid: buf.read_u32::<BigEndian>()?,
+                gid: get_cstr(buf)?,
+            }),
+            ROLLBACK_PREPARED_TAG => Self::RollbackPrepared(RollbackPreparedBody {
+                flags: buf.read_i8()?,
+                prepare_end_lsn: buf.read_u64::<BigEndian>()?,
+                rollback_end_lsn: buf.read_u64::<BigEndian>()?,
+                prepare_timestamp: buf.read_i64::<BigEndian>()?,
+                rollback_timestamp: buf.read_i64::<BigEndian>()?,
+                xid: buf.read_u32::<BigEndian>()?,
+                gid: get_cstr(buf)?,
+            }),
+            MESSAGE_TAG => {
+                let xid = read_stream_xid(buf, in_stream)?;
+                let flags = buf.read_u8()?;
+                let lsn = buf.read_u64::<BigEndian>()?;
+                let prefix = get_cstr(buf)?;
+                let len = buf.read_u32::<BigEndian>()?;
+                let content = buf.get_mut().split_to(len as usize);
+
+                Self::Message(MessageBody {
+                    xid,
+                    flags,
+                    lsn,
+                    prefix,
+                    content,
+                })
             }
             tag => {
                 return Err(io::Error::new(
@@ -231,6 +356,39 @@ impl Parse for LogicalReplicationMessage {
     }
 }
 
+/// Decodes a stream of pgoutput messages from a protocol version 2 (or later) subscription,
+/// automatically tracking whether decoding is currently inside a streamed (in-progress)
+/// transaction.
+///
+/// The plain [`Parse`] impl on [`LogicalReplicationMessage`] always decodes as if streaming
+/// were disabled, since it has no way to remember state between calls. Callers that enabled
+/// `streaming` via [`PgOutput`] should decode through this type instead (`two_phase` alone
+/// does not turn streaming on), so that the xid prefix on `Relation`/`Type`/`Insert`/`Update`/`Delete`/
+/// `Truncate`/`Message` bodies is read when, and only when, it is actually present on the wire.
+#[derive(Default)]
+pub struct LogicalReplicationStream {
+    in_stream: bool,
+}
+
+impl LogicalReplicationStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the next message, toggling the in-stream state on `StreamStart`/`StreamStop`.
+    pub fn decode(&mut self, buf: &mut BufReader<Bytes>) -> io::Result<LogicalReplicationMessage> {
+        let message = LogicalReplicationMessage::parse(buf, self.in_stream)?;
+
+        match message {
+            LogicalReplicationMessage::StreamStart(_) => self.in_stream = true,
+            LogicalReplicationMessage::StreamStop => self.in_stream = false,
+            _ => {}
+        }
+
+        Ok(message)
+    }
+}
+
 pub struct Tuple(Vec<TupleData>);
 
 impl Tuple {
@@ -297,6 +455,7 @@ pub enum TupleData {
     Null,
     Toast,
     Text(Bytes),
+    Binary(Bytes),
 }
 
 impl Parse for TupleData {
@@ -310,6 +469,10 @@ impl Parse for TupleData {
                 let len = buf.read_i32::<BigEndian>()?;
                 TupleData::Text(buf.get_mut().split_to(len as usize))
             }
+            TUPLE_DATA_BINARY_TAG => {
+                let len = buf.read_i32::<BigEndian>()?;
+                TupleData::Binary(buf.get_mut().split_to(len as usize))
+            }
             tag => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
@@ -405,6 +568,7 @@ pub enum ReplicaIdentity {
 }
 
 pub struct RelationBody {
+    xid: Option<u32>,
     rel_id: u32,
     namespace: Bytes,
     name: Bytes,
@@ -413,6 +577,13 @@ pub struct RelationBody {
 }
 
 impl RelationBody {
+    /// The transaction this relation description belongs to, if it was received as part of a
+    /// streamed (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn rel_id(&self) -> u32 {
         self.rel_id
@@ -440,12 +611,20 @@ impl RelationBody {
 }
 
 pub struct TypeBody {
+    xid: Option<u32>,
     id: u32,
     namespace: Bytes,
     name: Bytes,
 }
 
 impl TypeBody {
+    /// The transaction this type belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn id(&self) -> Oid {
         self.id
@@ -463,11 +642,19 @@ impl TypeBody {
 }
 
 pub struct InsertBody {
+    xid: Option<u32>,
     rel_id: u32,
     tuple: Tuple,
 }
 
 impl InsertBody {
+    /// The transaction this insert belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn rel_id(&self) -> u32 {
         self.rel_id
@@ -480,6 +667,7 @@ impl InsertBody {
 }
 
 pub struct UpdateBody {
+    xid: Option<u32>,
     rel_id: u32,
     old_tuple: Option<Tuple>,
     key_tuple: Option<Tuple>,
@@ -487,6 +675,13 @@ pub struct UpdateBody {
 }
 
 impl UpdateBody {
+    /// The transaction this update belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn rel_id(&self) -> u32 {
         self.rel_id
@@ -509,12 +704,20 @@ impl UpdateBody {
 }
 
 pub struct DeleteBody {
+    xid: Option<u32>,
     rel_id: u32,
     old_tuple: Option<Tuple>,
     key_tuple: Option<Tuple>,
 }
 
 impl DeleteBody {
+    /// The transaction this delete belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn rel_id(&self) -> u32 {
         self.rel_id
@@ -532,11 +735,19 @@ impl DeleteBody {
 }
 
 pub struct TruncateBody {
+    xid: Option<u32>,
     options: i8,
     rel_ids: Vec<u32>,
 }
 
 impl TruncateBody {
+    /// The transaction this truncate belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
     #[inline]
     pub fn rel_ids(&self) -> &[u32] {
         &self.rel_ids
@@ -548,6 +759,290 @@ impl TruncateBody {
     }
 }
 
+pub struct StreamStartBody {
+    xid: u32,
+    first_segment: bool,
+}
+
+impl StreamStartBody {
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    /// Whether this is the first stream of changes for this transaction.
+    #[inline]
+    pub fn first_segment(&self) -> bool {
+        self.first_segment
+    }
+}
+
+pub struct StreamCommitBody {
+    xid: u32,
+    flags: i8,
+    commit_lsn: u64,
+    end_lsn: u64,
+    timestamp: i64,
+}
+
+impl StreamCommitBody {
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn commit_lsn(&self) -> Lsn {
+        self.commit_lsn.into()
+    }
+
+    #[inline]
+    pub fn end_lsn(&self) -> Lsn {
+        self.end_lsn.into()
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+}
+
+pub struct StreamAbortBody {
+    xid: u32,
+    subxid: u32,
+}
+
+impl StreamAbortBody {
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    /// The subtransaction being aborted, which may be equal to `xid` itself when the whole
+    /// streamed transaction is aborted.
+    #[inline]
+    pub fn subxid(&self) -> u32 {
+        self.subxid
+    }
+}
+
+pub struct BeginPrepareBody {
+    prepare_lsn: u64,
+    end_lsn: u64,
+    timestamp: i64,
+    xid: u32,
+    gid: Bytes,
+}
+
+impl BeginPrepareBody {
+    #[inline]
+    pub fn prepare_lsn(&self) -> Lsn {
+        self.prepare_lsn.into()
+    }
+
+    #[inline]
+    pub fn end_lsn(&self) -> Lsn {
+        self.end_lsn.into()
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn gid(&self) -> io::Result<&str> {
+        get_str(&self.gid)
+    }
+}
+
+pub struct PrepareBody {
+    flags: i8,
+    prepare_lsn: u64,
+    end_lsn: u64,
+    timestamp: i64,
+    xid: u32,
+    gid: Bytes,
+}
+
+impl PrepareBody {
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn prepare_lsn(&self) -> Lsn {
+        self.prepare_lsn.into()
+    }
+
+    #[inline]
+    pub fn end_lsn(&self) -> Lsn {
+        self.end_lsn.into()
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn gid(&self) -> io::Result<&str> {
+        get_str(&self.gid)
+    }
+}
+
+pub struct CommitPreparedBody {
+    flags: i8,
+    commit_lsn: u64,
+    end_lsn: u64,
+    timestamp: i64,
+    xid: u32,
+    gid: Bytes,
+}
+
+impl CommitPreparedBody {
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn commit_lsn(&self) -> Lsn {
+        self.commit_lsn.into()
+    }
+
+    #[inline]
+    pub fn end_lsn(&self) -> Lsn {
+        self.end_lsn.into()
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn gid(&self) -> io::Result<&str> {
+        get_str(&self.gid)
+    }
+}
+
+pub struct RollbackPreparedBody {
+    flags: i8,
+    prepare_end_lsn: u64,
+    rollback_end_lsn: u64,
+    prepare_timestamp: i64,
+    rollback_timestamp: i64,
+    xid: u32,
+    gid: Bytes,
+}
+
+impl RollbackPreparedBody {
+    #[inline]
+    pub fn flags(&self) -> i8 {
+        self.flags
+    }
+
+    #[inline]
+    pub fn prepare_end_lsn(&self) -> Lsn {
+        self.prepare_end_lsn.into()
+    }
+
+    #[inline]
+    pub fn rollback_end_lsn(&self) -> Lsn {
+        self.rollback_end_lsn.into()
+    }
+
+    #[inline]
+    pub fn prepare_timestamp(&self) -> i64 {
+        self.prepare_timestamp
+    }
+
+    #[inline]
+    pub fn rollback_timestamp(&self) -> i64 {
+        self.rollback_timestamp
+    }
+
+    #[inline]
+    pub fn xid(&self) -> u32 {
+        self.xid
+    }
+
+    #[inline]
+    pub fn gid(&self) -> io::Result<&str> {
+        get_str(&self.gid)
+    }
+}
+
+pub struct MessageBody {
+    xid: Option<u32>,
+    flags: u8,
+    lsn: u64,
+    prefix: Bytes,
+    content: Bytes,
+}
+
+impl MessageBody {
+    /// The transaction this message belongs to, if it was received as part of a streamed
+    /// (in-progress) transaction.
+    #[inline]
+    pub fn xid(&self) -> Option<u32> {
+        self.xid
+    }
+
+    /// Whether this message was emitted as part of a transaction, as opposed to outside of
+    /// one (e.g. via a non-transactional call to `pg_logical_emit_message`).
+    #[inline]
+    pub fn is_transactional(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    #[inline]
+    pub fn lsn(&self) -> Lsn {
+        self.lsn.into()
+    }
+
+    #[inline]
+    pub fn prefix(&self) -> io::Result<&str> {
+        get_str(&self.prefix)
+    }
+
+    #[inline]
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+#[inline]
+fn read_stream_xid(buf: &mut BufReader<Bytes>, in_stream: bool) -> io::Result<Option<u32>> {
+    if in_stream {
+        Ok(Some(buf.read_u32::<BigEndian>()?))
+    } else {
+        Ok(None)
+    }
+}
+
 #[inline]
 fn find_null(buf: &[u8], start: usize) -> io::Result<usize> {
     match memchr(0, &buf[start..]) {
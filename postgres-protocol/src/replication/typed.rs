@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::replication::pgoutput::{RelationBody, Tuple, TupleData};
+use crate::Oid;
+
+/// Tracks the most recently received `RelationBody` for each `rel_id`.
+///
+/// `Insert`/`Update`/`Delete` bodies only carry a `rel_id`; the column names, OIDs and type
+/// modifiers needed to make sense of their tuples are sent separately as `Relation` messages.
+/// Caching those by `rel_id` lets callers turn a raw tuple into a [`TypedTuple`] without
+/// re-implementing that bookkeeping themselves.
+#[derive(Default)]
+pub struct RelationCache {
+    relations: HashMap<u32, RelationBody>,
+}
+
+impl RelationCache {
+    pub fn new() -> Self {
+        Self {
+            relations: HashMap::new(),
+        }
+    }
+
+    /// Records a `Relation` message, replacing any previous definition for the same `rel_id`.
+    pub fn record(&mut self, relation: RelationBody) {
+        self.relations.insert(relation.rel_id(), relation);
+    }
+
+    /// Returns the cached relation for `rel_id`, if one has been recorded.
+    #[inline]
+    pub fn get(&self, rel_id: u32) -> Option<&RelationBody> {
+        self.relations.get(&rel_id)
+    }
+
+    /// Pairs `tuple` with the column metadata of its relation, looked up by `rel_id`.
+    ///
+    /// Returns `None` if no `Relation` message for `rel_id` has been recorded yet.
+    pub fn typed_tuple<'a>(&'a self, rel_id: u32, tuple: &'a Tuple) -> Option<TypedTuple<'a>> {
+        self.get(rel_id).map(|relation| TypedTuple { relation, tuple })
+    }
+}
+
+/// Which wire representation a column's value was sent in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Binary,
+}
+
+/// A column's value as carried by a tuple, before any `FromSql`-style decoding is applied.
+pub enum ColumnValue<'a> {
+    /// The column is NULL.
+    Null,
+    /// The column's value is unchanged and was not included in the tuple, typically because it
+    /// is a TOASTed value the publisher didn't have to send.
+    Unchanged,
+    /// The column was sent in the given wire format.
+    Value(Format, &'a [u8]),
+}
+
+/// The result of decoding a single column's value through a caller-provided decode function.
+pub enum DecodedValue<T> {
+    /// The column is NULL.
+    Null,
+    /// The column's value is unchanged and was not included in the tuple.
+    Unchanged,
+    /// The column was decoded successfully.
+    Value(T),
+}
+
+/// A tuple paired with the column metadata of the relation it belongs to.
+///
+/// This is the typed counterpart of a raw [`Tuple`]: it knows each column's name and OID, so
+/// values can be resolved through a decoder (e.g. the crate's `FromSql` machinery) instead of
+/// being matched on blindly.
+pub struct TypedTuple<'a> {
+    relation: &'a RelationBody,
+    tuple: &'a Tuple,
+}
+
+impl<'a> TypedTuple<'a> {
+    /// Iterates over this tuple's columns, pairing each value with its column name and type OID.
+    pub fn columns(&self) -> impl Iterator<Item = io::Result<(&'a str, Oid, ColumnValue<'a>)>> {
+        self.relation
+            .columns()
+            .iter()
+            .zip(self.tuple.tuple_data())
+            .map(|(column, data)| {
+                let value = match data {
+                    TupleData::Null => ColumnValue::Null,
+                    TupleData::Toast => ColumnValue::Unchanged,
+                    TupleData::Text(bytes) => ColumnValue::Value(Format::Text, bytes),
+                    TupleData::Binary(bytes) => ColumnValue::Value(Format::Binary, bytes),
+                };
+                Ok((column.name()?, column.type_id() as Oid, value))
+            })
+    }
+
+    /// Decodes the column at `index` using `decode`, which receives the column's type OID, its
+    /// wire format and its raw bytes.
+    ///
+    /// `postgres-protocol` only deals with the wire format, so plugging in an actual decoder
+    /// (e.g. `postgres_types::FromSql`) is left to the caller rather than depend on it here.
+    pub fn decode<T>(
+        &self,
+        index: usize,
+        decode: impl FnOnce(Oid, Format, &'a [u8]) -> io::Result<T>,
+    ) -> io::Result<DecodedValue<T>> {
+        let out_of_bounds = || {
+            io::Error::new(io::ErrorKind::InvalidInput, "column index out of bounds")
+        };
+
+        let column = self.relation.columns().get(index).ok_or_else(out_of_bounds)?;
+        let data = self.tuple.tuple_data().get(index).ok_or_else(out_of_bounds)?;
+
+        match data {
+            TupleData::Null => Ok(DecodedValue::Null),
+            TupleData::Toast => Ok(DecodedValue::Unchanged),
+            TupleData::Text(bytes) => {
+                decode(column.type_id() as Oid, Format::Text, bytes).map(DecodedValue::Value)
+            }
+            TupleData::Binary(bytes) => {
+                decode(column.type_id() as Oid, Format::Binary, bytes).map(DecodedValue::Value)
+            }
+        }
+    }
+}